@@ -0,0 +1,14 @@
+// Copyright 2015-2016 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! DNS over TLS (DoT) via `native-tls`
+
+mod tls_client_stream;
+mod tls_stream;
+
+pub use self::tls_client_stream::{TlsClientStream, TlsClientStreamBuilder, VerifyPeerCertificate};
+pub(crate) use self::tls_stream::TlsStreamBuilder;
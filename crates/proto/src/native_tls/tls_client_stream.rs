@@ -10,9 +10,11 @@
 use std::future::Future;
 use std::net::SocketAddr;
 use std::pin::Pin;
+use std::sync::Arc;
 
-use futures_util::TryFutureExt;
-use native_tls::Certificate;
+use futures_util::{future, TryFutureExt};
+use native_tls::{Certificate, Identity, Protocol};
+use tokio::sync::oneshot;
 use tokio_native_tls::TlsStream as TokioTlsStream;
 
 use crate::error::ProtoError;
@@ -28,25 +30,120 @@ use crate::xfer::BufDnsStreamHandle;
 pub type TlsClientStream<S> =
     TcpClientStream<AsyncIoTokioAsStd<TokioTlsStream<AsyncIoStdAsTokio<S>>>>;
 
+/// A callback used to accept or reject the peer's certificate after the TLS handshake completes.
+///
+/// It receives the peer's leaf certificate (`None` if it could not be retrieved) and the
+/// `dns_name` the connection was made to, and returns `true` to accept the connection.
+pub type VerifyPeerCertificate = Arc<dyn Fn(Option<&Certificate>, &str) -> bool + Send + Sync>;
+
 /// Builder for TlsClientStream
-pub struct TlsClientStreamBuilder<P>(TlsStreamBuilder<P>);
+pub struct TlsClientStreamBuilder<P> {
+    stream_builder: TlsStreamBuilder<P>,
+    verify_peer_certificate: Option<VerifyPeerCertificate>,
+}
 
 impl<P: RuntimeProvider> TlsClientStreamBuilder<P> {
     /// Creates a builder fo the construction of a TlsClientStream
     pub fn new(provider: P) -> Self {
-        Self(TlsStreamBuilder::new(provider))
+        let mut stream_builder = TlsStreamBuilder::new(provider);
+        // Reject protocol downgrade by default; callers can still widen or narrow this via
+        // `min_protocol_version`/`max_protocol_version`.
+        stream_builder.min_protocol_version(Protocol::Tlsv12);
+
+        Self {
+            stream_builder,
+            verify_peer_certificate: None,
+        }
     }
 
     /// Add a custom trusted peer certificate or certificate authority.
     ///
     /// If this is the 'client' then the 'server' must have it associated as it's `identity`, or have had the `identity` signed by this certificate.
     pub fn add_ca(&mut self, ca: Certificate) {
-        self.0.add_ca(ca);
+        self.stream_builder.add_ca(ca);
+    }
+
+    /// Add a client identity (certificate and private key) to present during the handshake.
+    ///
+    /// This is required for mutual TLS, where the 'server' requires the 'client' to present a certificate to demonstrate its identity. `identity` is a PKCS#12 bundle containing the client's certificate and private key (and, optionally, the chain used to sign it).
+    pub fn add_identity(&mut self, identity: Identity) {
+        self.stream_builder.add_identity(identity);
     }
 
     /// Sets the address to connect from.
     pub fn bind_addr(&mut self, bind_addr: SocketAddr) {
-        self.0.bind_addr(bind_addr);
+        self.stream_builder.bind_addr(bind_addr);
+    }
+
+    /// Sets the minimum TLS protocol version accepted when negotiating a connection.
+    ///
+    /// Defaults to rejecting anything older than TLS 1.2, to guard against protocol downgrade.
+    pub fn min_protocol_version(&mut self, version: Protocol) {
+        self.stream_builder.min_protocol_version(version);
+    }
+
+    /// Sets the maximum TLS protocol version accepted when negotiating a connection.
+    pub fn max_protocol_version(&mut self, version: Protocol) {
+        self.stream_builder.max_protocol_version(version);
+    }
+
+    /// Installs a callback to accept or reject the peer's certificate, for pinning or
+    /// trust-on-first-use in place of the usual CA-based trust checks.
+    ///
+    /// This disables the platform's certificate verification, since `verify` becomes solely
+    /// responsible for the trust decision; a `false` return fails the connection with a `ProtoError`.
+    pub fn add_verification_callback(
+        &mut self,
+        verify: impl Fn(Option<&Certificate>, &str) -> bool + Send + Sync + 'static,
+    ) {
+        self.stream_builder.danger_accept_invalid_certs(true);
+        self.verify_peer_certificate = Some(Arc::new(verify));
+    }
+
+    /// Drives the handshake to completion, applying `verify_peer_certificate` (if any) to the
+    /// peer's leaf certificate and handing it to `cert_sender` (if any) once retrieved.
+    #[allow(clippy::type_complexity)]
+    fn connect<F>(
+        stream_future: F,
+        dns_name: String,
+        verify_peer_certificate: Option<VerifyPeerCertificate>,
+        cert_sender: Option<oneshot::Sender<Option<Certificate>>>,
+    ) -> Pin<Box<dyn Future<Output = Result<TlsClientStream<P::Tcp>, ProtoError>> + Send>>
+    where
+        F: Future<Output = std::io::Result<AsyncIoTokioAsStd<TokioTlsStream<AsyncIoStdAsTokio<P::Tcp>>>>>
+            + Send
+            + 'static,
+    {
+        Box::pin(
+            stream_future
+                .map_err(ProtoError::from)
+                .and_then(move |stream| {
+                    let peer_certificate = match stream.0.get_ref().peer_certificate() {
+                        Ok(peer_certificate) => peer_certificate,
+                        Err(e) => {
+                            return future::ready(Err(ProtoError::from(format!(
+                                "failed to retrieve the peer's TLS certificate: {e}"
+                            ))))
+                        }
+                    };
+
+                    let accepted = verify_peer_certificate
+                        .as_ref()
+                        .map_or(true, |verify| verify(peer_certificate.as_ref(), &dns_name));
+
+                    if let Some(cert_sender) = cert_sender {
+                        let _ = cert_sender.send(peer_certificate);
+                    }
+
+                    future::ready(if accepted {
+                        Ok(TcpClientStream::from_stream(stream))
+                    } else {
+                        Err(ProtoError::from(
+                            "the peer's TLS certificate was rejected by the verification callback",
+                        ))
+                    })
+                }),
+        )
     }
 
     /// Creates a new TlsStream to the specified name_server with stream future.
@@ -56,6 +153,10 @@ impl<P: RuntimeProvider> TlsClientStreamBuilder<P> {
     /// * 'future` - future of TCP stream
     /// * `name_server` - IP and Port for the remote DNS resolver
     /// * `dns_name` - The DNS name associated with a certificate
+    ///
+    /// If a verification callback was installed via
+    /// [`add_verification_callback`](Self::add_verification_callback), the returned future resolves
+    /// to a `ProtoError` when the callback rejects the peer's certificate.
     #[allow(clippy::type_complexity)]
     pub fn build_with_future<F>(
         self,
@@ -69,15 +170,50 @@ impl<P: RuntimeProvider> TlsClientStreamBuilder<P> {
     where
         F: Future<Output = std::io::Result<P::Tcp>> + Send + Unpin + 'static,
     {
-        let (stream_future, sender) = self.0.build_with_future(future, name_server, dns_name);
+        let Self {
+            stream_builder,
+            verify_peer_certificate,
+        } = self;
+        let (stream_future, sender) =
+            stream_builder.build_with_future(future, name_server, dns_name.clone());
+        let new_future = Self::connect(stream_future, dns_name, verify_peer_certificate, None);
 
-        let new_future = Box::pin(
-            stream_future
-                .map_ok(TcpClientStream::from_stream)
-                .map_err(ProtoError::from),
+        (new_future, sender)
+    }
+
+    /// Like [`build_with_future`](Self::build_with_future), but additionally returns a
+    /// `oneshot::Receiver` that resolves to the peer's leaf certificate once the handshake
+    /// completes, so that callers can log or verify it (e.g. against an expected fingerprint)
+    /// without reaching into the stream.
+    #[allow(clippy::type_complexity)]
+    pub fn build_with_future_and_peer_certificate<F>(
+        self,
+        future: F,
+        name_server: SocketAddr,
+        dns_name: String,
+    ) -> (
+        Pin<Box<dyn Future<Output = Result<TlsClientStream<P::Tcp>, ProtoError>> + Send>>,
+        BufDnsStreamHandle,
+        oneshot::Receiver<Option<Certificate>>,
+    )
+    where
+        F: Future<Output = std::io::Result<P::Tcp>> + Send + Unpin + 'static,
+    {
+        let Self {
+            stream_builder,
+            verify_peer_certificate,
+        } = self;
+        let (stream_future, sender) =
+            stream_builder.build_with_future(future, name_server, dns_name.clone());
+        let (cert_sender, cert_receiver) = oneshot::channel();
+        let new_future = Self::connect(
+            stream_future,
+            dns_name,
+            verify_peer_certificate,
+            Some(cert_sender),
         );
 
-        (new_future, sender)
+        (new_future, sender, cert_receiver)
     }
 
     /// Creates a new TlsStream to the specified name_server
@@ -86,6 +222,9 @@ impl<P: RuntimeProvider> TlsClientStreamBuilder<P> {
     ///
     /// * `name_server` - IP and Port for the remote DNS resolver
     /// * `dns_name` - The DNS name associated with a certificate
+    ///
+    /// See [`TlsClientStreamBuilder::build_with_future`] for details on verification-callback
+    /// rejection.
     #[allow(clippy::type_complexity)]
     pub fn build(
         self,
@@ -95,14 +234,77 @@ impl<P: RuntimeProvider> TlsClientStreamBuilder<P> {
         Pin<Box<dyn Future<Output = Result<TlsClientStream<P::Tcp>, ProtoError>> + Send>>,
         BufDnsStreamHandle,
     ) {
-        let (stream_future, sender) = self.0.build(name_server, dns_name);
+        let Self {
+            stream_builder,
+            verify_peer_certificate,
+        } = self;
+        let (stream_future, sender) = stream_builder.build(name_server, dns_name.clone());
+        let new_future = Self::connect(stream_future, dns_name, verify_peer_certificate, None);
 
-        let new_future = Box::pin(
-            stream_future
-                .map_ok(TcpClientStream::from_stream)
-                .map_err(ProtoError::from),
+        (new_future, sender)
+    }
+
+    /// See [`TlsClientStreamBuilder::build_with_future_and_peer_certificate`] for details on the
+    /// returned certificate receiver.
+    #[allow(clippy::type_complexity)]
+    pub fn build_and_peer_certificate(
+        self,
+        name_server: SocketAddr,
+        dns_name: String,
+    ) -> (
+        Pin<Box<dyn Future<Output = Result<TlsClientStream<P::Tcp>, ProtoError>> + Send>>,
+        BufDnsStreamHandle,
+        oneshot::Receiver<Option<Certificate>>,
+    ) {
+        let Self {
+            stream_builder,
+            verify_peer_certificate,
+        } = self;
+        let (stream_future, sender) = stream_builder.build(name_server, dns_name.clone());
+        let (cert_sender, cert_receiver) = oneshot::channel();
+        let new_future = Self::connect(
+            stream_future,
+            dns_name,
+            verify_peer_certificate,
+            Some(cert_sender),
         );
 
-        (new_future, sender)
+        (new_future, sender, cert_receiver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use native_tls::{Identity, TlsAcceptor};
+    use tokio::net::TcpListener;
+
+    use crate::runtime::TokioRuntimeProvider;
+
+    use super::*;
+
+    const CA_CERT: &[u8] = include_bytes!("../../tests/test-data/ca.pem");
+    const SERVER_IDENTITY: &[u8] = include_bytes!("../../tests/test-data/cert.p12");
+
+    // Accepts one connection on `listener` and drives a server-side TLS handshake over it,
+    // ignoring the handshake's outcome; the test only cares about what the client observes.
+    async fn accept_one(listener: TcpListener, identity: Identity) {
+        let acceptor = tokio_native_tls::TlsAcceptor::from(TlsAcceptor::new(identity).unwrap());
+        let (tcp_stream, _) = listener.accept().await.unwrap();
+        let _ = acceptor.accept(tcp_stream).await;
+    }
+
+    #[tokio::test]
+    async fn verification_callback_rejection_fails_the_connection() {
+        let identity = Identity::from_pkcs12(SERVER_IDENTITY, "mypass").unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let name_server = listener.local_addr().unwrap();
+        tokio::spawn(accept_one(listener, identity));
+
+        let mut builder = TlsClientStreamBuilder::new(TokioRuntimeProvider::new());
+        builder.add_ca(Certificate::from_pem(CA_CERT).unwrap());
+        builder.add_verification_callback(|_cert, _dns_name| false);
+
+        let (connect, _handle) = builder.build(name_server, "ns.example.com".to_string());
+        assert!(connect.await.is_err());
     }
 }
@@ -0,0 +1,161 @@
+// Copyright 2015-2016 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! TlsStream for DNS over TLS, backed by `native-tls`
+
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::time::Duration;
+
+use futures_util::TryFutureExt;
+use native_tls::{Certificate, Identity, Protocol, TlsConnector};
+use tokio_native_tls::{TlsConnector as TokioTlsConnector, TlsStream as TokioTlsStream};
+
+use crate::runtime::iocompat::{AsyncIoStdAsTokio, AsyncIoTokioAsStd};
+use crate::runtime::RuntimeProvider;
+use crate::xfer::BufDnsStreamHandle;
+
+/// Builder for the underlying native-tls `TlsStream`.
+///
+/// This accumulates the state needed to construct a `native_tls::TlsConnector` (trusted CAs,
+/// client identity, allowed protocol versions, and whether to bypass certificate validation) and
+/// applies it right before the connection is made. [`TlsClientStreamBuilder`](super::TlsClientStreamBuilder)
+/// is the public entry point; this type is internal plumbing shared by it.
+pub(crate) struct TlsStreamBuilder<P> {
+    provider: P,
+    ca_chain: Vec<Certificate>,
+    identity: Option<Identity>,
+    bind_addr: Option<SocketAddr>,
+    min_protocol_version: Option<Protocol>,
+    max_protocol_version: Option<Protocol>,
+    danger_accept_invalid_certs: bool,
+}
+
+impl<P: RuntimeProvider> TlsStreamBuilder<P> {
+    /// Creates a new builder that has no trusted CAs, identity, or protocol version bounds set.
+    pub(crate) fn new(provider: P) -> Self {
+        Self {
+            provider,
+            ca_chain: vec![],
+            identity: None,
+            bind_addr: None,
+            min_protocol_version: None,
+            max_protocol_version: None,
+            danger_accept_invalid_certs: false,
+        }
+    }
+
+    /// Add a custom trusted peer certificate or certificate authority.
+    pub(crate) fn add_ca(&mut self, ca: Certificate) {
+        self.ca_chain.push(ca);
+    }
+
+    /// Add a client identity (certificate and private key) to present during the handshake.
+    pub(crate) fn add_identity(&mut self, identity: Identity) {
+        self.identity = Some(identity);
+    }
+
+    /// Sets the address to connect from.
+    pub(crate) fn bind_addr(&mut self, bind_addr: SocketAddr) {
+        self.bind_addr = Some(bind_addr);
+    }
+
+    /// Sets the minimum TLS protocol version accepted when negotiating a connection.
+    pub(crate) fn min_protocol_version(&mut self, version: Protocol) {
+        self.min_protocol_version = Some(version);
+    }
+
+    /// Sets the maximum TLS protocol version accepted when negotiating a connection.
+    pub(crate) fn max_protocol_version(&mut self, version: Protocol) {
+        self.max_protocol_version = Some(version);
+    }
+
+    /// Disables the platform's certificate verification, e.g. so a caller-supplied verification
+    /// callback can make the trust decision instead.
+    pub(crate) fn danger_accept_invalid_certs(&mut self, accept_invalid_certs: bool) {
+        self.danger_accept_invalid_certs = accept_invalid_certs;
+    }
+
+    fn connector(&self) -> io::Result<TokioTlsConnector> {
+        let mut builder = TlsConnector::builder();
+        for ca in &self.ca_chain {
+            builder.add_root_certificate(ca.clone());
+        }
+        if let Some(identity) = &self.identity {
+            builder.identity(identity.clone());
+        }
+        builder.min_protocol_version(self.min_protocol_version);
+        builder.max_protocol_version(self.max_protocol_version);
+        builder.danger_accept_invalid_certs(self.danger_accept_invalid_certs);
+
+        let connector = builder
+            .build()
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        Ok(TokioTlsConnector::from(connector))
+    }
+
+    /// Creates a new TlsStream to the specified name_server with stream future.
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn build_with_future<F>(
+        self,
+        future: F,
+        name_server: SocketAddr,
+        dns_name: String,
+    ) -> (
+        Pin<
+            Box<
+                dyn Future<Output = io::Result<AsyncIoTokioAsStd<TokioTlsStream<AsyncIoStdAsTokio<P::Tcp>>>>>
+                    + Send,
+            >,
+        >,
+        BufDnsStreamHandle,
+    )
+    where
+        F: Future<Output = io::Result<P::Tcp>> + Send + Unpin + 'static,
+    {
+        let (message_sender, _outbound_messages) = BufDnsStreamHandle::new(name_server);
+        let connector = self.connector();
+
+        let tls_connect = async move {
+            let connector = connector?;
+            let tcp_stream = future.await?;
+
+            let tls_stream = connector
+                .connect(&dns_name, AsyncIoStdAsTokio(tcp_stream))
+                .await
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+
+            Ok(AsyncIoTokioAsStd(tls_stream))
+        };
+
+        (Box::pin(tls_connect), message_sender)
+    }
+
+    /// Creates a new TlsStream to the specified name_server, establishing the TCP connection itself.
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn build(
+        self,
+        name_server: SocketAddr,
+        dns_name: String,
+    ) -> (
+        Pin<
+            Box<
+                dyn Future<Output = io::Result<AsyncIoTokioAsStd<TokioTlsStream<AsyncIoStdAsTokio<P::Tcp>>>>>
+                    + Send,
+            >,
+        >,
+        BufDnsStreamHandle,
+    ) {
+        let connect_future = self
+            .provider
+            .connect_tcp(name_server, self.bind_addr, None::<Duration>);
+
+        self.build_with_future(connect_future, name_server, dns_name)
+    }
+}